@@ -0,0 +1,572 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::bandwidth::{BandwidthMeter, BandwidthStats};
+use crate::bootstrap_cache::BootstrapCache;
+use crate::compression::{self, Codec, DEFAULT_COMPRESSION_THRESHOLD_BYTES};
+use crate::config::Config;
+use crate::error::{QuicP2pError, Result};
+use crate::event::Event;
+use crate::gossip::{self, GossipState};
+use crate::node_info::NodeInfo;
+use crate::peer::Peer;
+use crate::peer_manager::{PeerManager, ReportSource};
+use crate::rpc::{self, RequestId, RequestTable, DEFAULT_REQUEST_TIMEOUT};
+use crate::service_flags::ServiceFlags;
+use crate::transport::{self, Connection};
+use crate::wire_msg::WireMsg;
+use bytes::Bytes;
+use crossbeam_channel as mpmc;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default number of bootstrap candidates tried concurrently by `QuicP2p::bootstrap`.
+const DEFAULT_BOOTSTRAP_CONCURRENCY: usize = 3;
+
+/// Score penalty applied to a peer that sends a frame tagged with a codec we fail to
+/// decompress it with, via `ReportSource::Framing`.
+const FRAMING_VIOLATION_PENALTY: i32 = -20;
+
+/// A single, lightweight handle to a QUIC peer-to-peer endpoint.
+///
+/// Constructed via [`Builder`](crate::Builder). Cloning a `QuicP2p` is cheap and yields another
+/// handle to the same underlying endpoint and connection set.
+#[derive(Clone)]
+pub struct QuicP2p {
+    pub(crate) config: Config,
+    pub(crate) our_info: NodeInfo,
+    pub(crate) event_tx: mpmc::Sender<Event>,
+    pub(crate) connections: Arc<Mutex<HashMap<SocketAddr, NodeInfo>>>,
+    pub(crate) gossip: Arc<Mutex<GossipState>>,
+    pub(crate) requests: Arc<Mutex<RequestTable>>,
+    pub(crate) bootstrap_cache: Arc<Mutex<BootstrapCache>>,
+    pub(crate) peer_manager: Arc<Mutex<PeerManager>>,
+    pub(crate) negotiated_codecs: Arc<Mutex<HashMap<SocketAddr, Codec>>>,
+    pub(crate) negotiated_services: Arc<Mutex<HashMap<SocketAddr, ServiceFlags>>>,
+    pub(crate) sockets: Arc<Mutex<HashMap<SocketAddr, Arc<Connection>>>>,
+    pub(crate) uncompressed_bytes_sent: Arc<AtomicU64>,
+    pub(crate) compressed_bytes_sent: Arc<AtomicU64>,
+    pub(crate) bandwidth: Arc<Mutex<BandwidthMeter>>,
+}
+
+impl QuicP2p {
+    /// Sends `msg` to `peer`, connecting first if there is no existing connection. Payloads
+    /// over the configured threshold are transparently compressed with whichever codec was
+    /// negotiated for this peer.
+    pub fn send(&mut self, peer: Peer, msg: Bytes) {
+        let peer_addr = peer.peer_addr();
+        if let Peer::Node { node_info } = &peer {
+            self.connect_to(node_info.clone());
+        }
+        self.send_wire(peer_addr, WireMsg::UserMsg(msg));
+    }
+
+    /// Bincode-encodes `wire_msg`, compresses it if it's over the configured threshold and a
+    /// codec has been negotiated with `peer_addr`, and writes the resulting frame to that
+    /// peer's socket. Silently drops the frame if we have no connection to `peer_addr` (the
+    /// caller is expected to have connected first, as `send` and `connect_to` do).
+    fn send_wire(&mut self, peer_addr: SocketAddr, wire_msg: WireMsg) {
+        let payload = Bytes::from(bincode::serialize(&wire_msg).unwrap_or_default());
+
+        let threshold = self
+            .config
+            .compression_threshold_bytes
+            .unwrap_or(DEFAULT_COMPRESSION_THRESHOLD_BYTES);
+        let codec = if payload.len() > threshold {
+            unwrap_lock(&self.negotiated_codecs)
+                .get(&peer_addr)
+                .copied()
+                .unwrap_or(Codec::None)
+        } else {
+            Codec::None
+        };
+
+        let framed = compression::encode(codec, &payload);
+        self.uncompressed_bytes_sent
+            .fetch_add(payload.len() as u64, Ordering::Relaxed);
+        self.compressed_bytes_sent
+            .fetch_add(framed.len() as u64, Ordering::Relaxed);
+        unwrap_lock(&self.bandwidth).record_sent(peer_addr, framed.len() as u64);
+
+        if let Some(conn) = unwrap_lock(&self.sockets).get(&peer_addr).cloned() {
+            let _ = conn.send_frame(&framed);
+        }
+    }
+
+    /// Called by the transport layer whenever a complete frame arrives from `peer_addr`:
+    /// records bandwidth, decompresses and decodes the envelope, then dispatches it to the
+    /// matching `on_*_received` handler. A frame we can't decompress or parse is a protocol
+    /// violation rather than something to silently drop or mis-deliver.
+    pub(crate) fn on_frame_received(&mut self, peer_addr: SocketAddr, framed: Bytes) {
+        unwrap_lock(&self.bandwidth).record_received(peer_addr, framed.len() as u64);
+
+        let payload = match compression::decode(&framed) {
+            Ok(payload) => payload,
+            Err(_) => {
+                self.report_peer(peer_addr, ReportSource::Framing, FRAMING_VIOLATION_PENALTY);
+                return;
+            }
+        };
+        let wire_msg: WireMsg = match bincode::deserialize(&payload) {
+            Ok(wire_msg) => wire_msg,
+            Err(_) => {
+                self.report_peer(peer_addr, ReportSource::Framing, FRAMING_VIOLATION_PENALTY);
+                return;
+            }
+        };
+
+        match wire_msg {
+            WireMsg::Handshake {
+                services,
+                preferred_codec,
+            } => self.on_handshake_received(peer_addr, services, preferred_codec),
+            WireMsg::UserMsg(msg) => self.on_user_msg_received(peer_addr, msg),
+            WireMsg::Request { request_id, msg } => {
+                let peer = self.peer_for(peer_addr);
+                self.on_request_received(peer, request_id, msg);
+            }
+            WireMsg::Response { request_id, msg } => self.on_response_received(request_id, msg),
+            WireMsg::Gossip { topic, msg } => self.on_gossip_received(topic, peer_addr, msg),
+            WireMsg::GossipTopicAnnouncement { topic } => {
+                self.on_topic_announcement(&topic, peer_addr)
+            }
+        }
+    }
+
+    /// Called once a `WireMsg::UserMsg` frame has been decompressed and decoded: raises
+    /// `Event::NewMessage` with the original application payload.
+    pub(crate) fn on_user_msg_received(&mut self, peer_addr: SocketAddr, msg: Bytes) {
+        let _ = self.event_tx.send(Event::NewMessage { peer_addr, msg });
+    }
+
+    /// Sends our own capabilities and preferred codec to `peer_addr` as the very first frame on
+    /// a newly-established connection, ahead of anything `send`/`send_request`/gossip might
+    /// queue up.
+    pub(crate) fn send_handshake(&mut self, peer_addr: SocketAddr) {
+        let services = self.our_info.services;
+        let preferred_codec = self.config.compression_codec.unwrap_or_default();
+        self.send_wire(
+            peer_addr,
+            WireMsg::Handshake {
+                services,
+                preferred_codec,
+            },
+        );
+    }
+
+    /// Called once a `WireMsg::Handshake` frame has been decoded: records the peer's real
+    /// advertised capabilities and preferred codec, updating its `NodeInfo` in `connections` if
+    /// we dialled it, so `peers_with`/`peer_for`/`send_wire` all reflect what the peer actually
+    /// told us instead of a caller-supplied guess or our own configuration.
+    fn on_handshake_received(
+        &mut self,
+        peer_addr: SocketAddr,
+        services: ServiceFlags,
+        preferred_codec: Codec,
+    ) {
+        unwrap_lock(&self.negotiated_services).insert(peer_addr, services);
+        unwrap_lock(&self.negotiated_codecs).insert(peer_addr, preferred_codec);
+        if let Some(node_info) = unwrap_lock(&self.connections).get_mut(&peer_addr) {
+            node_info.services = services;
+        }
+    }
+
+    /// Builds the `Peer` a frame from `peer_addr` should be attributed to: a full `Peer::Node`
+    /// if we have its `NodeInfo` on file (i.e. we dialled it via `connect_to`), otherwise a
+    /// `Peer::Client` carrying whatever capabilities it negotiated via the handshake.
+    fn peer_for(&self, peer_addr: SocketAddr) -> Peer {
+        match unwrap_lock(&self.connections).get(&peer_addr) {
+            Some(node_info) => Peer::Node {
+                node_info: node_info.clone(),
+            },
+            None => {
+                let services = unwrap_lock(&self.negotiated_services)
+                    .get(&peer_addr)
+                    .copied()
+                    .unwrap_or_else(ServiceFlags::empty);
+                Peer::Client { peer_addr, services }
+            }
+        }
+    }
+
+    /// Called by the transport layer when a peer's socket has been closed, locally or by the
+    /// remote end, so we tidy up the same way an explicit `disconnect_from` would.
+    pub(crate) fn on_socket_closed(&mut self, peer_addr: SocketAddr) {
+        self.disconnect_from(peer_addr);
+    }
+
+    /// Returns a snapshot of global and per-peer bandwidth usage: cumulative byte counters plus
+    /// a sliding-window bytes/sec estimate for each.
+    pub fn bandwidth_stats(&self) -> BandwidthStats {
+        unwrap_lock(&self.bandwidth).snapshot()
+    }
+
+    /// Spawns a background thread that raises `Event::BandwidthReport` every `interval`, until
+    /// the receiving end of the event channel is dropped.
+    pub(crate) fn spawn_bandwidth_reporter(&self, interval: Duration) {
+        let bandwidth = Arc::clone(&self.bandwidth);
+        let event_tx = self.event_tx.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let stats = unwrap_lock(&bandwidth).snapshot();
+            if event_tx.send(Event::BandwidthReport { stats }).is_err() {
+                return;
+            }
+        });
+    }
+
+    /// Returns `(uncompressed_bytes_sent, compressed_bytes_sent)` accumulated across every frame
+    /// sent (user messages, requests, responses, gossip and handshakes alike), so the efficiency
+    /// of compression is measurable.
+    pub fn compression_stats(&self) -> (u64, u64) {
+        (
+            self.uncompressed_bytes_sent.load(Ordering::Relaxed),
+            self.compressed_bytes_sent.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Initiates a connection to `node_info`, if one does not already exist. Refused if the
+    /// peer is currently banned or would put us over the per-IP connection limit; if accepting
+    /// it pushes us over the total connection limit, the lowest-scoring non-reserved peer is
+    /// evicted to make room (if that is the new peer itself, it is disconnected again and no
+    /// `Event::ConnectedTo` is raised).
+    pub fn connect_to(&mut self, node_info: NodeInfo) {
+        let mut peer_manager = unwrap_lock(&self.peer_manager);
+        if peer_manager.ban_status(node_info.peer_addr).is_some() {
+            return;
+        }
+        if !peer_manager.ip_limit_ok(node_info.peer_addr) {
+            return;
+        }
+        drop(peer_manager);
+
+        if unwrap_lock(&self.connections).contains_key(&node_info.peer_addr) {
+            return;
+        }
+        if transport::connect(self, node_info.peer_addr).is_err() {
+            let _ = self.event_tx.send(Event::ConnectionFailure {
+                peer_addr: node_info.peer_addr,
+            });
+            return;
+        }
+
+        let mut peer_manager = unwrap_lock(&self.peer_manager);
+        let evicted = peer_manager.on_connected(&node_info, true);
+        drop(peer_manager);
+
+        if let Some(evicted_addr) = evicted {
+            self.disconnect_from(evicted_addr);
+            if evicted_addr == node_info.peer_addr {
+                return;
+            }
+        }
+
+        // The peer's handshake may already have arrived; its advertised capabilities take
+        // precedence over whatever the caller supplied.
+        let mut node_info = node_info;
+        if let Some(services) = unwrap_lock(&self.negotiated_services).get(&node_info.peer_addr) {
+            node_info.services = *services;
+        }
+        unwrap_lock(&self.connections).insert(node_info.peer_addr, node_info.clone());
+
+        let _ = self.event_tx.send(Event::ConnectedTo {
+            peer: Peer::Node { node_info },
+        });
+    }
+
+    /// Drops the connection to `peer_addr`, if any, cancelling any requests still awaiting a
+    /// response from it.
+    pub fn disconnect_from(&mut self, peer_addr: SocketAddr) {
+        let mut connections = unwrap_lock(&self.connections);
+        connections.remove(&peer_addr);
+        drop(connections);
+        unwrap_lock(&self.requests).cancel_for_peer(peer_addr);
+        unwrap_lock(&self.peer_manager).on_disconnected(peer_addr);
+        let mut gossip = unwrap_lock(&self.gossip);
+        gossip.on_peer_disconnected(peer_addr);
+        gossip.repair_meshes();
+        drop(gossip);
+        unwrap_lock(&self.negotiated_codecs).remove(&peer_addr);
+        unwrap_lock(&self.negotiated_services).remove(&peer_addr);
+        unwrap_lock(&self.bandwidth).forget_peer(peer_addr);
+        if let Some(conn) = unwrap_lock(&self.sockets).remove(&peer_addr) {
+            conn.shutdown();
+        }
+    }
+
+    /// Lets application code (or this crate's own gossip/RPC paths) feed observed peer
+    /// (mis)behaviour into the scoring system. A large enough accumulation of negative reports
+    /// bans the peer and raises `Event::PeerBanned`.
+    pub fn report_peer(&mut self, peer_addr: SocketAddr, source: ReportSource, penalty: i32) {
+        let banned_until = unwrap_lock(&self.peer_manager).adjust_score(peer_addr, source, penalty);
+        if let Some(until) = banned_until {
+            self.disconnect_from(peer_addr);
+            let _ = self.event_tx.send(Event::PeerBanned { peer_addr, until });
+        }
+    }
+
+    /// Sends `msg` to `peer` as a request, returning the id used to correlate the eventual
+    /// response and a receiver that yields the response payload (or nothing, if the request
+    /// times out or the peer disconnects first).
+    pub fn send_request(&mut self, peer: Peer, msg: Bytes) -> (RequestId, mpmc::Receiver<Bytes>) {
+        let request_id = rpc::next_request_id();
+        let peer_addr = peer.peer_addr();
+        let timeout = self
+            .config
+            .request_timeout_msec
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+
+        let reply_rx = unwrap_lock(&self.requests).insert(request_id, peer_addr, timeout);
+        if let Peer::Node { node_info } = &peer {
+            self.connect_to(node_info.clone());
+        }
+        self.send_wire(peer_addr, WireMsg::Request { request_id, msg });
+        self.spawn_timeout_watcher(request_id, timeout);
+        (request_id, reply_rx)
+    }
+
+    /// Sends `msg` back to the peer that raised `Event::IncomingRequest { request_id, .. }`.
+    pub fn send_response(&mut self, peer: Peer, request_id: RequestId, msg: Bytes) {
+        self.send_wire(peer.peer_addr(), WireMsg::Response { request_id, msg });
+    }
+
+    /// Called when a `WireMsg::Request` frame arrives from `peer`.
+    pub(crate) fn on_request_received(&mut self, peer: Peer, request_id: RequestId, msg: Bytes) {
+        let _ = self
+            .event_tx
+            .send(Event::IncomingRequest { request_id, peer, msg });
+    }
+
+    /// Called when a `WireMsg::Response` frame arrives; resolves the matching `send_request`
+    /// caller if one is still waiting.
+    pub(crate) fn on_response_received(&mut self, request_id: RequestId, msg: Bytes) {
+        let _ = unwrap_lock(&self.requests).resolve(request_id, msg);
+    }
+
+    fn spawn_timeout_watcher(&self, request_id: RequestId, timeout: Duration) {
+        let requests = Arc::clone(&self.requests);
+        let event_tx = self.event_tx.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            if unwrap_lock(&requests).take_if_expired(request_id) {
+                let _ = event_tx.send(Event::RequestTimeout { request_id });
+            }
+        });
+    }
+
+    /// Returns our own `NodeInfo`, which can be shared with peers so they can connect to us.
+    pub fn our_connection_info(&mut self) -> Result<NodeInfo> {
+        Ok(self.our_info.clone())
+    }
+
+    /// Returns every currently-connected peer whose negotiated capabilities include every flag
+    /// set in `required`, e.g. `qp2p.peers_with(ServiceFlags::GOSSIP)` to find fan-out targets.
+    /// Peers that connected to us (rather than being dialled via `connect_to`) are included once
+    /// their handshake has arrived, carrying only their address and negotiated capabilities.
+    pub fn peers_with(&self, required: ServiceFlags) -> Vec<NodeInfo> {
+        let connections = unwrap_lock(&self.connections);
+        let mut peers: Vec<NodeInfo> = connections
+            .values()
+            .filter(|node_info| node_info.services.contains(required))
+            .cloned()
+            .collect();
+        for (peer_addr, services) in unwrap_lock(&self.negotiated_services).iter() {
+            if !connections.contains_key(peer_addr) && services.contains(required) {
+                peers.push(NodeInfo {
+                    peer_addr: *peer_addr,
+                    peer_cert_der: Vec::new(),
+                    services: *services,
+                });
+            }
+        }
+        peers
+    }
+
+    /// Walks the persistent bootstrap cache (most-recently-seen first) followed by
+    /// `Config::hard_coded_contacts`, dialling up to `bootstrap_concurrency` candidates at once
+    /// in each batch and moving on to the next batch only once every dial in the current one has
+    /// settled. Raises `Event::BootstrapedTo` on success or `Event::BootstrapFailure` once every
+    /// candidate has been exhausted.
+    pub fn bootstrap(&mut self) {
+        let candidates = unwrap_lock(&self.bootstrap_cache)
+            .ordered_candidates(&self.config.hard_coded_contacts);
+        if candidates.is_empty() {
+            let _ = self.event_tx.send(Event::BootstrapFailure);
+            return;
+        }
+
+        let concurrency = self
+            .config
+            .bootstrap_concurrency
+            .unwrap_or(DEFAULT_BOOTSTRAP_CONCURRENCY)
+            .max(1);
+
+        for batch in candidates.chunks(concurrency) {
+            let (result_tx, result_rx) = mpmc::bounded(batch.len());
+            for node_info in batch {
+                let mut qp2p = self.clone();
+                let node_info = node_info.clone();
+                let result_tx = result_tx.clone();
+                std::thread::spawn(move || {
+                    let succeeded = qp2p.try_bootstrap_to(node_info);
+                    let _ = result_tx.send(succeeded);
+                });
+            }
+            drop(result_tx);
+            if result_rx.iter().any(|succeeded| succeeded) {
+                return;
+            }
+        }
+        let _ = self.event_tx.send(Event::BootstrapFailure);
+    }
+
+    /// Attempts a single bootstrap candidate, updating the cache and raising
+    /// `Event::BootstrapedTo` on success. Returns whether the connection actually succeeded, so
+    /// `bootstrap` knows whether to try the next candidate.
+    fn try_bootstrap_to(&mut self, node_info: NodeInfo) -> bool {
+        self.connect_to(node_info.clone());
+
+        if !unwrap_lock(&self.connections).contains_key(&node_info.peer_addr) {
+            unwrap_lock(&self.bootstrap_cache).record_failure(node_info.peer_addr);
+            return false;
+        }
+
+        let mut cache = unwrap_lock(&self.bootstrap_cache);
+        cache.record_success(node_info.clone());
+        let _ = cache.save();
+        drop(cache);
+
+        let _ = self.event_tx.send(Event::BootstrapedTo { node: node_info });
+        true
+    }
+
+    /// Subscribes to `topic`, joining (and helping forward messages for) its gossip mesh.
+    pub fn subscribe(&mut self, topic: &str) {
+        unwrap_lock(&self.gossip).subscribe(topic);
+        self.announce_topic(topic);
+    }
+
+    /// Publishes `msg` on `topic`, flooding it to every peer currently in the topic's mesh.
+    pub fn publish(&mut self, topic: &str, msg: Bytes) {
+        let id = gossip::message_id(topic, &msg);
+        let already_seen = unwrap_lock(&self.gossip).check_and_insert_seen(id);
+        if already_seen {
+            return;
+        }
+        self.forward_gossip(topic, msg, None);
+    }
+
+    /// Called when a `WireMsg::Gossip` frame arrives from `source`: de-duplicates it, emits
+    /// `Event::GossipMessage` on first sight, and forwards it on to the rest of the mesh.
+    pub(crate) fn on_gossip_received(&mut self, topic: String, source: SocketAddr, msg: Bytes) {
+        let id = gossip::message_id(&topic, &msg);
+        let already_seen = unwrap_lock(&self.gossip).check_and_insert_seen(id);
+        if already_seen {
+            return;
+        }
+        let _ = self.event_tx.send(Event::GossipMessage {
+            topic: topic.clone(),
+            source,
+            msg: msg.clone(),
+        });
+        self.forward_gossip(&topic, msg, Some(source));
+    }
+
+    /// Called when a `WireMsg::GossipTopicAnnouncement` frame arrives from a connected peer,
+    /// growing the mesh if it is below the low-water mark.
+    pub(crate) fn on_topic_announcement(&mut self, topic: &str, peer: SocketAddr) {
+        let node_info = match self.peer_for(peer) {
+            Peer::Node { node_info } => node_info,
+            Peer::Client { peer_addr, services } => NodeInfo {
+                peer_addr,
+                peer_cert_der: Vec::new(),
+                services,
+            },
+        };
+        let mut gossip = unwrap_lock(&self.gossip);
+        gossip.on_topic_announcement(topic, node_info);
+        gossip.repair_meshes();
+    }
+
+    fn forward_gossip(&mut self, topic: &str, msg: Bytes, sender: Option<SocketAddr>) {
+        let targets = unwrap_lock(&self.gossip).forward_targets(topic, sender);
+        for addr in targets {
+            self.send_wire(
+                addr,
+                WireMsg::Gossip {
+                    topic: topic.to_string(),
+                    msg: msg.clone(),
+                },
+            );
+        }
+    }
+
+    fn announce_topic(&mut self, topic: &str) {
+        let peer_addrs: Vec<SocketAddr> = unwrap_lock(&self.connections).keys().copied().collect();
+        for peer_addr in peer_addrs {
+            self.announce_topic_to(peer_addr, topic);
+        }
+    }
+
+    fn announce_topic_to(&mut self, peer_addr: SocketAddr, topic: &str) {
+        self.send_wire(
+            peer_addr,
+            WireMsg::GossipTopicAnnouncement {
+                topic: topic.to_string(),
+            },
+        );
+    }
+
+    /// Re-announces every topic we know about (subscribed to locally, or learned of from a
+    /// peer's own announcement) to every currently-connected peer. Called periodically by
+    /// `spawn_gossip_announcer`, since a one-shot announcement at `subscribe` time alone can't
+    /// repair a mesh that forms afterwards, or propagate membership through a peer that relays
+    /// gossip without ever subscribing to the topic itself.
+    pub(crate) fn announce_known_topics(&mut self) {
+        let topics = unwrap_lock(&self.gossip).known_topics();
+        for topic in topics {
+            self.announce_topic(&topic);
+        }
+    }
+
+    /// Announces every topic we know about directly to `peer_addr`, so a newly-established
+    /// connection doesn't have to wait for the next periodic re-announce before meshes can form
+    /// across it.
+    pub(crate) fn announce_known_topics_to(&mut self, peer_addr: SocketAddr) {
+        let topics = unwrap_lock(&self.gossip).known_topics();
+        for topic in topics {
+            self.announce_topic_to(peer_addr, &topic);
+        }
+    }
+
+    /// Spawns a background thread that calls `announce_known_topics` every `interval`. The
+    /// thread holds its own clone of this handle and runs for the rest of the process's lifetime.
+    pub(crate) fn spawn_gossip_announcer(&self, interval: Duration) {
+        let mut qp2p = self.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            qp2p.announce_known_topics();
+        });
+    }
+}
+
+pub(crate) fn unwrap_lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+pub(crate) fn new_error(msg: impl Into<String>) -> QuicP2pError {
+    QuicP2pError::EndpointBuild(msg.into())
+}
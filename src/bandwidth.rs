@@ -0,0 +1,224 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Bandwidth metering for the send/receive paths: cumulative byte counters plus a sliding-window
+//! rate estimate, both globally and per-peer, so slow or abusive peers can be spotted without
+//! instrumenting every call site by hand.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Width of the sliding window used to estimate instantaneous throughput.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Snapshot of one peer's bandwidth usage at the time `bandwidth_stats` was called.
+#[derive(Clone, Debug, Default)]
+pub struct PeerBandwidth {
+    /// Total bytes sent to this peer over the connection's lifetime.
+    pub bytes_sent: u64,
+    /// Total bytes received from this peer over the connection's lifetime.
+    pub bytes_received: u64,
+    /// Estimated outbound bytes/sec over the last `RATE_WINDOW`.
+    pub send_rate: f64,
+    /// Estimated inbound bytes/sec over the last `RATE_WINDOW`.
+    pub recv_rate: f64,
+}
+
+/// Snapshot returned by `QuicP2p::bandwidth_stats` (and periodically emitted as
+/// `Event::BandwidthReport`, if configured).
+#[derive(Clone, Debug, Default)]
+pub struct BandwidthStats {
+    /// Total bytes sent across every connection.
+    pub global_bytes_sent: u64,
+    /// Total bytes received across every connection.
+    pub global_bytes_received: u64,
+    /// Estimated global outbound bytes/sec over the last `RATE_WINDOW`.
+    pub global_send_rate: f64,
+    /// Estimated global inbound bytes/sec over the last `RATE_WINDOW`.
+    pub global_recv_rate: f64,
+    /// Per-peer breakdown, keyed by peer address.
+    pub per_peer: HashMap<SocketAddr, PeerBandwidth>,
+}
+
+#[derive(Default)]
+struct PeerEntry {
+    sent_total: u64,
+    received_total: u64,
+    sent_samples: VecDeque<(Instant, u64)>,
+    received_samples: VecDeque<(Instant, u64)>,
+}
+
+/// Tracks bandwidth usage globally and per-peer.
+#[derive(Default)]
+pub(crate) struct BandwidthMeter {
+    global_bytes_sent: u64,
+    global_bytes_received: u64,
+    global_sent_samples: VecDeque<(Instant, u64)>,
+    global_received_samples: VecDeque<(Instant, u64)>,
+    per_peer: HashMap<SocketAddr, PeerEntry>,
+}
+
+impl BandwidthMeter {
+    pub(crate) fn record_sent(&mut self, peer_addr: SocketAddr, bytes: u64) {
+        let now = Instant::now();
+        self.global_bytes_sent += bytes;
+        self.global_sent_samples.push_back((now, bytes));
+        prune(&mut self.global_sent_samples, now);
+
+        let entry = self.per_peer.entry(peer_addr).or_default();
+        entry.sent_total += bytes;
+        entry.sent_samples.push_back((now, bytes));
+        prune(&mut entry.sent_samples, now);
+    }
+
+    pub(crate) fn record_received(&mut self, peer_addr: SocketAddr, bytes: u64) {
+        let now = Instant::now();
+        self.global_bytes_received += bytes;
+        self.global_received_samples.push_back((now, bytes));
+        prune(&mut self.global_received_samples, now);
+
+        let entry = self.per_peer.entry(peer_addr).or_default();
+        entry.received_total += bytes;
+        entry.received_samples.push_back((now, bytes));
+        prune(&mut entry.received_samples, now);
+    }
+
+    pub(crate) fn forget_peer(&mut self, peer_addr: SocketAddr) {
+        self.per_peer.remove(&peer_addr);
+    }
+
+    /// Builds a full snapshot of current totals and sliding-window rates.
+    pub(crate) fn snapshot(&mut self) -> BandwidthStats {
+        let now = Instant::now();
+        prune(&mut self.global_sent_samples, now);
+        prune(&mut self.global_received_samples, now);
+
+        let per_peer = self
+            .per_peer
+            .iter_mut()
+            .map(|(addr, entry)| {
+                prune(&mut entry.sent_samples, now);
+                prune(&mut entry.received_samples, now);
+                (
+                    *addr,
+                    PeerBandwidth {
+                        bytes_sent: entry.sent_total,
+                        bytes_received: entry.received_total,
+                        send_rate: rate(&entry.sent_samples, now),
+                        recv_rate: rate(&entry.received_samples, now),
+                    },
+                )
+            })
+            .collect();
+
+        BandwidthStats {
+            global_bytes_sent: self.global_bytes_sent,
+            global_bytes_received: self.global_bytes_received,
+            global_send_rate: rate(&self.global_sent_samples, now),
+            global_recv_rate: rate(&self.global_received_samples, now),
+            per_peer,
+        }
+    }
+}
+
+fn prune(samples: &mut VecDeque<(Instant, u64)>, now: Instant) {
+    while let Some((ts, _)) = samples.front() {
+        if now.duration_since(*ts) > RATE_WINDOW {
+            samples.pop_front();
+        } else {
+            break;
+        }
+    }
+}
+
+/// Estimates bytes/sec from `samples`, dividing by how long they've actually been accumulating
+/// rather than by the full `RATE_WINDOW` so a connection only a second old doesn't look 10x
+/// slower than it really is.
+fn rate(samples: &VecDeque<(Instant, u64)>, now: Instant) -> f64 {
+    let total: u64 = samples.iter().map(|(_, bytes)| bytes).sum();
+    let oldest = match samples.front() {
+        Some((ts, _)) => *ts,
+        None => return 0.0,
+    };
+    let elapsed = now.duration_since(oldest).as_secs_f64().max(1.0);
+    total as f64 / elapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 1))
+    }
+
+    #[test]
+    fn record_sent_and_received_accumulate_global_and_per_peer_totals() {
+        let mut meter = BandwidthMeter::default();
+        meter.record_sent(addr(), 100);
+        meter.record_sent(addr(), 50);
+        meter.record_received(addr(), 30);
+
+        let stats = meter.snapshot();
+        assert_eq!(stats.global_bytes_sent, 150);
+        assert_eq!(stats.global_bytes_received, 30);
+
+        let peer = &stats.per_peer[&addr()];
+        assert_eq!(peer.bytes_sent, 150);
+        assert_eq!(peer.bytes_received, 30);
+    }
+
+    #[test]
+    fn rate_is_positive_once_bytes_have_been_recorded() {
+        let mut meter = BandwidthMeter::default();
+        meter.record_sent(addr(), 1000);
+
+        let stats = meter.snapshot();
+        assert!(stats.global_send_rate > 0.0);
+        assert!(stats.per_peer[&addr()].send_rate > 0.0);
+    }
+
+    #[test]
+    fn rate_of_an_untouched_sample_set_is_zero() {
+        let samples = VecDeque::new();
+        assert_eq!(rate(&samples, Instant::now()), 0.0);
+    }
+
+    #[test]
+    fn prune_drops_samples_older_than_the_rate_window() {
+        let mut samples = VecDeque::new();
+        let now = Instant::now();
+        samples.push_back((now - RATE_WINDOW - Duration::from_secs(1), 100));
+        samples.push_back((now, 50));
+
+        prune(&mut samples, now);
+
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].1, 50);
+    }
+
+    #[test]
+    fn forget_peer_removes_it_from_future_snapshots() {
+        let mut meter = BandwidthMeter::default();
+        meter.record_sent(addr(), 10);
+        meter.forget_peer(addr());
+
+        assert!(!meter.snapshot().per_peer.contains_key(&addr()));
+    }
+
+    #[test]
+    fn snapshot_of_an_empty_meter_has_zeroed_totals() {
+        let mut meter = BandwidthMeter::default();
+        let stats = meter.snapshot();
+        assert_eq!(stats.global_bytes_sent, 0);
+        assert_eq!(stats.global_recv_rate, 0.0);
+        assert!(stats.per_peer.is_empty());
+    }
+}
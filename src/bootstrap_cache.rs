@@ -0,0 +1,201 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Persists peers we've successfully bootstrapped through before, so a restarted node can
+//! reconnect to the live network instead of depending solely on `Config::hard_coded_contacts`.
+
+use crate::error::{QuicP2pError, Result};
+use crate::node_info::NodeInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A cache entry is evicted once it has failed this many times in a row.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// Reliability record for one previously-seen peer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    node_info: NodeInfo,
+    successes: u32,
+    consecutive_failures: u32,
+    last_seen_unix_secs: u64,
+}
+
+/// Tracks peers we've successfully contacted before and persists them to disk, so a future
+/// `QuicP2p::bootstrap` call can try the most recently-seen peers first instead of picking a
+/// hard-coded contact at random. The order depends on last-seen time only; success counts do
+/// not affect it, and repeated failures only matter in that they evict an entry.
+#[derive(Default)]
+pub(crate) struct BootstrapCache {
+    path: Option<PathBuf>,
+    entries: HashMap<SocketAddr, CacheEntry>,
+}
+
+impl BootstrapCache {
+    /// Loads the cache from `path`, if it exists. A missing file is treated as an empty cache,
+    /// since this is expected the first time a node ever runs.
+    pub(crate) fn load(path: Option<PathBuf>) -> Result<Self> {
+        let entries = match &path {
+            Some(path) if path.exists() => {
+                let raw = fs::read_to_string(path)?;
+                serde_json::from_str(&raw)
+                    .map_err(|e| QuicP2pError::Serialisation(e.to_string()))?
+            }
+            _ => HashMap::new(),
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Writes the cache back out to disk, if a path was configured.
+    pub(crate) fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let raw = serde_json::to_string_pretty(&self.entries)
+                .map_err(|e| QuicP2pError::Serialisation(e.to_string()))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, raw)?;
+        }
+        Ok(())
+    }
+
+    /// Records a successful contact, resetting its failure streak and touching its last-seen
+    /// timestamp so it sorts ahead of staler entries.
+    pub(crate) fn record_success(&mut self, node_info: NodeInfo) {
+        let entry = self
+            .entries
+            .entry(node_info.peer_addr)
+            .or_insert_with(|| CacheEntry {
+                node_info: node_info.clone(),
+                successes: 0,
+                consecutive_failures: 0,
+                last_seen_unix_secs: 0,
+            });
+        entry.node_info = node_info;
+        entry.successes += 1;
+        entry.consecutive_failures = 0;
+        entry.last_seen_unix_secs = unix_now();
+    }
+
+    /// Records a failed contact attempt, evicting the entry once it has failed too many times
+    /// in a row.
+    pub(crate) fn record_failure(&mut self, peer_addr: SocketAddr) {
+        let evict = match self.entries.get_mut(&peer_addr) {
+            Some(entry) => {
+                entry.consecutive_failures += 1;
+                entry.consecutive_failures >= MAX_CONSECUTIVE_FAILURES
+            }
+            None => false,
+        };
+        if evict {
+            self.entries.remove(&peer_addr);
+        }
+    }
+
+    /// Returns bootstrap candidates ordered by most-recently-seen first, with `hard_coded`
+    /// contacts not already present in the cache appended as a fallback.
+    pub(crate) fn ordered_candidates(&self, hard_coded: &[NodeInfo]) -> Vec<NodeInfo> {
+        let mut cached: Vec<&CacheEntry> = self.entries.values().collect();
+        cached.sort_by_key(|e| std::cmp::Reverse(e.last_seen_unix_secs));
+
+        let mut candidates: Vec<NodeInfo> =
+            cached.into_iter().map(|e| e.node_info.clone()).collect();
+        for contact in hard_coded {
+            if !self.entries.contains_key(&contact.peer_addr) {
+                candidates.push(contact.clone());
+            }
+        }
+        candidates
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_info(port: u16) -> NodeInfo {
+        NodeInfo {
+            peer_addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            peer_cert_der: Vec::new(),
+            services: Default::default(),
+        }
+    }
+
+    fn cache_with(entries: Vec<(NodeInfo, u64)>) -> BootstrapCache {
+        let mut cache = BootstrapCache::default();
+        for (node_info, last_seen_unix_secs) in entries {
+            cache.entries.insert(
+                node_info.peer_addr,
+                CacheEntry {
+                    node_info,
+                    successes: 1,
+                    consecutive_failures: 0,
+                    last_seen_unix_secs,
+                },
+            );
+        }
+        cache
+    }
+
+    #[test]
+    fn ordered_candidates_puts_most_recently_seen_first() {
+        let oldest = node_info(1);
+        let newest = node_info(2);
+        let cache = cache_with(vec![(oldest.clone(), 10), (newest.clone(), 20)]);
+
+        assert_eq!(cache.ordered_candidates(&[]), vec![newest, oldest]);
+    }
+
+    #[test]
+    fn ordered_candidates_appends_hard_coded_contacts_not_already_cached() {
+        let cached = node_info(1);
+        let hard_coded_only = node_info(2);
+        let cache = cache_with(vec![(cached.clone(), 10)]);
+
+        assert_eq!(
+            cache.ordered_candidates(&[cached.clone(), hard_coded_only.clone()]),
+            vec![cached, hard_coded_only]
+        );
+    }
+
+    #[test]
+    fn record_failure_evicts_after_max_consecutive_failures() {
+        let peer = node_info(1);
+        let mut cache = cache_with(vec![(peer.clone(), 10)]);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES - 1 {
+            cache.record_failure(peer.peer_addr);
+            assert!(cache.entries.contains_key(&peer.peer_addr));
+        }
+        cache.record_failure(peer.peer_addr);
+        assert!(!cache.entries.contains_key(&peer.peer_addr));
+    }
+
+    #[test]
+    fn record_success_resets_failure_streak() {
+        let peer = node_info(1);
+        let mut cache = cache_with(vec![(peer.clone(), 10)]);
+
+        cache.record_failure(peer.peer_addr);
+        cache.record_success(peer.clone());
+
+        assert_eq!(cache.entries[&peer.peer_addr].consecutive_failures, 0);
+    }
+}
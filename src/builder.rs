@@ -0,0 +1,96 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::api::{self, QuicP2p};
+use crate::bandwidth::BandwidthMeter;
+use crate::bootstrap_cache::BootstrapCache;
+use crate::config::Config;
+use crate::error::Result;
+use crate::event::Event;
+use crate::gossip::{self, GossipState};
+use crate::node_info::NodeInfo;
+use crate::peer_manager::PeerManager;
+use crate::rpc::RequestTable;
+use crate::transport;
+use crossbeam_channel as mpmc;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Builds a [`QuicP2p`] instance.
+pub struct Builder {
+    event_tx: mpmc::Sender<Event>,
+    config: Config,
+}
+
+impl Builder {
+    /// Starts a new builder. Events raised by the resulting `QuicP2p` are sent on `event_tx`.
+    pub fn new(event_tx: mpmc::Sender<Event>) -> Self {
+        Self {
+            event_tx,
+            config: Config::default(),
+        }
+    }
+
+    /// Overrides the default configuration.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Consumes the builder, returning a ready-to-use `QuicP2p`.
+    pub fn build(self) -> Result<QuicP2p> {
+        let ip = self.config.ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let port = self.config.port.unwrap_or(0);
+        let our_info = NodeInfo {
+            peer_addr: SocketAddr::new(ip, port),
+            peer_cert_der: Vec::new(),
+            services: self.config.our_services,
+        };
+        let bootstrap_cache = BootstrapCache::load(self.config.bootstrap_cache_path.clone())?;
+        let peer_manager = PeerManager::new(
+            self.config.max_connections,
+            self.config.outbound_headroom_factor,
+            self.config.max_connections_per_ip,
+        );
+
+        let bandwidth_report_interval = self.config.bandwidth_report_interval_msec;
+
+        let mut qp2p = QuicP2p {
+            config: self.config,
+            our_info,
+            event_tx: self.event_tx,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            gossip: Arc::new(Mutex::new(GossipState::default())),
+            requests: Arc::new(Mutex::new(RequestTable::default())),
+            bootstrap_cache: Arc::new(Mutex::new(bootstrap_cache)),
+            peer_manager: Arc::new(Mutex::new(peer_manager)),
+            negotiated_codecs: Arc::new(Mutex::new(HashMap::new())),
+            negotiated_services: Arc::new(Mutex::new(HashMap::new())),
+            sockets: Arc::new(Mutex::new(HashMap::new())),
+            uncompressed_bytes_sent: Arc::new(AtomicU64::new(0)),
+            compressed_bytes_sent: Arc::new(AtomicU64::new(0)),
+            bandwidth: Arc::new(Mutex::new(BandwidthMeter::default())),
+        };
+
+        let bound_addr = transport::listen(&qp2p).map_err(|e| api::new_error(e.to_string()))?;
+        qp2p.our_info.peer_addr = bound_addr;
+
+        if let Some(interval_msec) = bandwidth_report_interval {
+            if interval_msec > 0 {
+                qp2p.spawn_bandwidth_reporter(Duration::from_millis(interval_msec));
+            }
+        }
+        qp2p.spawn_gossip_announcer(gossip::ANNOUNCE_INTERVAL);
+
+        Ok(qp2p)
+    }
+}
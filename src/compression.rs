@@ -0,0 +1,155 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Transparent payload compression, negotiated per-connection so both ends agree on a codec
+//! before either one starts tagging frames with it.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Payload size, in bytes, above which `QuicP2p::send` will attempt to compress before
+/// sending, if a non-`None` codec was negotiated with the peer.
+pub const DEFAULT_COMPRESSION_THRESHOLD_BYTES: usize = 16 * 1024;
+
+/// A compression codec both ends of a connection have agreed to use.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Codec {
+    /// No compression; the payload is sent as-is.
+    #[default]
+    None,
+    /// LZ4 block compression: fast, modest ratio.
+    Lz4,
+    /// Snappy compression: fast, similar ratio to LZ4.
+    Snappy,
+}
+
+impl Codec {
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Lz4 => 1,
+            Codec::Snappy => 2,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Codec> {
+        match tag {
+            0 => Some(Codec::None),
+            1 => Some(Codec::Lz4),
+            2 => Some(Codec::Snappy),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Codec::None => "none",
+            Codec::Lz4 => "lz4",
+            Codec::Snappy => "snappy",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Codec::None),
+            "lz4" => Ok(Codec::Lz4),
+            "snappy" => Ok(Codec::Snappy),
+            other => Err(format!("unknown compression codec: {}", other)),
+        }
+    }
+}
+
+/// Compresses `payload` with `codec` and prefixes it with a one-byte codec tag, so the receiver
+/// can decompress without any side-channel. `Codec::None` is tagged but left uncompressed.
+pub(crate) fn encode(codec: Codec, payload: &Bytes) -> Bytes {
+    let body = match codec {
+        Codec::None => payload.to_vec(),
+        Codec::Lz4 => lz4::block::compress(payload, None, true).unwrap_or_else(|_| payload.to_vec()),
+        Codec::Snappy => snap::raw::Encoder::new()
+            .compress_vec(payload)
+            .unwrap_or_else(|_| payload.to_vec()),
+    };
+    let mut framed = Vec::with_capacity(body.len() + 1);
+    framed.push(codec.tag());
+    framed.extend_from_slice(&body);
+    Bytes::from(framed)
+}
+
+/// Reverses `encode`: reads the codec tag and decompresses the remainder, returning the
+/// original payload. An unrecognised tag is treated as `Codec::None` so forward-compatible
+/// codecs added later degrade gracefully instead of corrupting the stream. A tag we do
+/// recognise but fail to decompress is a genuine protocol violation, not something to paper
+/// over by handing the caller back the still-compressed bytes.
+pub(crate) fn decode(framed: &Bytes) -> Result<Bytes, String> {
+    if framed.is_empty() {
+        return Ok(framed.clone());
+    }
+    let codec = Codec::from_tag(framed[0]).unwrap_or(Codec::None);
+    let body = &framed[1..];
+    match codec {
+        Codec::None => Ok(Bytes::from(body.to_vec())),
+        Codec::Lz4 => lz4::block::decompress(body, None)
+            .map(Bytes::from)
+            .map_err(|e| format!("lz4 decompression failed: {}", e)),
+        Codec::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(body)
+            .map(Bytes::from)
+            .map_err(|e| format!("snappy decompression failed: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trips(codec: Codec) {
+        let payload = Bytes::from(b"the quick brown fox jumps over the lazy dog".repeat(64));
+        let framed = encode(codec, &payload);
+        let decoded = decode(&framed).expect("decode should succeed for data we just encoded");
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn none_round_trips() {
+        round_trips(Codec::None);
+    }
+
+    #[test]
+    fn lz4_round_trips() {
+        round_trips(Codec::Lz4);
+    }
+
+    #[test]
+    fn snappy_round_trips() {
+        round_trips(Codec::Snappy);
+    }
+
+    #[test]
+    fn decode_of_empty_input_is_empty() {
+        assert_eq!(decode(&Bytes::new()).unwrap(), Bytes::new());
+    }
+
+    #[test]
+    fn truncated_lz4_body_is_a_decode_error() {
+        let payload = Bytes::from(b"some payload worth compressing".repeat(8));
+        let framed = encode(Codec::Lz4, &payload);
+        let mut truncated = framed.to_vec();
+        truncated.truncate(truncated.len() - 4);
+        assert!(decode(&Bytes::from(truncated)).is_err());
+    }
+}
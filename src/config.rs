@@ -0,0 +1,98 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::compression::Codec;
+use crate::node_info::NodeInfo;
+use crate::service_flags::ServiceFlags;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Wraps `Vec<NodeInfo>` so `structopt` parses the whole `--hard-coded-contacts` value as one
+/// JSON array instead of treating it as a repeatable, per-occurrence flag (its default
+/// behaviour for any bare `Vec<T>` field).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HardCodedContacts(pub Vec<NodeInfo>);
+
+impl Deref for HardCodedContacts {
+    type Target = [NodeInfo];
+
+    fn deref(&self) -> &[NodeInfo] {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for HardCodedContacts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+            .map(HardCodedContacts)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Configuration for a `QuicP2p` instance, parseable from the command line via `structopt`
+/// or constructed directly by embedding applications.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Config {
+    /// IP address to bind to. If not supplied we bind to `0.0.0.0` (all interfaces).
+    #[structopt(long)]
+    pub ip: Option<IpAddr>,
+    /// Port to bind to. If not supplied a random port is chosen.
+    #[structopt(long)]
+    pub port: Option<u16>,
+    /// Hard-coded peers to bootstrap from when nothing better is known.
+    #[structopt(long, default_value = "[]")]
+    pub hard_coded_contacts: HardCodedContacts,
+    /// Max message size we'll accept from a peer, in bytes.
+    #[structopt(long)]
+    pub max_msg_size_allowed: Option<u32>,
+    /// Milliseconds of inactivity before an idle connection is closed.
+    #[structopt(long)]
+    pub idle_timeout_msec: Option<u64>,
+    /// Milliseconds to wait for a response to an outgoing request before giving up and raising
+    /// `Event::RequestTimeout`. Defaults to `rpc::DEFAULT_REQUEST_TIMEOUT` if unset.
+    #[structopt(long)]
+    pub request_timeout_msec: Option<u64>,
+    /// Path to the JSON file used to persist the bootstrap cache across restarts. If unset, the
+    /// cache is kept in memory only for the lifetime of this process.
+    #[structopt(long, parse(from_os_str))]
+    pub bootstrap_cache_path: Option<PathBuf>,
+    /// Maximum number of bootstrap candidates `QuicP2p::bootstrap` will have in flight at once.
+    #[structopt(long)]
+    pub bootstrap_concurrency: Option<usize>,
+    /// Maximum number of simultaneous peer connections.
+    #[structopt(long)]
+    pub max_connections: Option<usize>,
+    /// Fraction of `max_connections` reserved for outbound-only peers, protecting them from
+    /// eviction pressure caused by a flood of inbound connections.
+    #[structopt(long)]
+    pub outbound_headroom_factor: Option<f32>,
+    /// Maximum number of simultaneous connections accepted from a single IP address.
+    #[structopt(long)]
+    pub max_connections_per_ip: Option<usize>,
+    /// Capabilities this node advertises to peers during the handshake.
+    #[structopt(long, default_value = "0")]
+    pub our_services: ServiceFlags,
+    /// Compression codec to negotiate with peers for large payloads. Falls back to no
+    /// compression for peers that didn't negotiate a codec.
+    #[structopt(long)]
+    pub compression_codec: Option<Codec>,
+    /// Payload size, in bytes, above which `send` attempts to compress using the negotiated
+    /// codec.
+    #[structopt(long)]
+    pub compression_threshold_bytes: Option<usize>,
+    /// If set, `Event::BandwidthReport` snapshots are raised on this interval, in milliseconds.
+    #[structopt(long)]
+    pub bandwidth_report_interval_msec: Option<u64>,
+}
@@ -0,0 +1,57 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::fmt;
+use std::io;
+
+/// Result type used throughout this crate.
+pub type Result<T> = std::result::Result<T, QuicP2pError>;
+
+/// Errors that can occur when using this crate.
+#[derive(Debug)]
+pub enum QuicP2pError {
+    /// QUIC endpoint could not be constructed.
+    EndpointBuild(String),
+    /// No connection exists to the given peer.
+    NoSuchConnection,
+    /// No bootstrap contacts were configured or discovered.
+    NoEndpointEchoServerFound,
+    /// An I/O error occurred.
+    Io(io::Error),
+    /// Failed to (de)serialize a message.
+    Serialisation(String),
+    /// A peer violated the wire protocol.
+    ProtocolViolation(String),
+    /// The operation timed out.
+    TimedOut,
+}
+
+impl fmt::Display for QuicP2pError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuicP2pError::EndpointBuild(e) => write!(f, "could not build QUIC endpoint: {}", e),
+            QuicP2pError::NoSuchConnection => write!(f, "no connection to requested peer"),
+            QuicP2pError::NoEndpointEchoServerFound => {
+                write!(f, "no bootstrap contacts available")
+            }
+            QuicP2pError::Io(e) => write!(f, "I/O error: {}", e),
+            QuicP2pError::Serialisation(e) => write!(f, "(de)serialisation error: {}", e),
+            QuicP2pError::ProtocolViolation(e) => write!(f, "protocol violation: {}", e),
+            QuicP2pError::TimedOut => write!(f, "operation timed out"),
+        }
+    }
+}
+
+impl std::error::Error for QuicP2pError {}
+
+impl From<io::Error> for QuicP2pError {
+    fn from(e: io::Error) -> Self {
+        QuicP2pError::Io(e)
+    }
+}
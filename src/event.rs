@@ -0,0 +1,87 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::bandwidth::BandwidthStats;
+use crate::node_info::NodeInfo;
+use crate::peer::Peer;
+use crate::rpc::RequestId;
+use bytes::Bytes;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+/// Events raised by `QuicP2p` and delivered to the application over the channel
+/// given to `Builder::new`.
+#[derive(Debug)]
+pub enum Event {
+    /// A new connection was established with `peer`.
+    ConnectedTo {
+        /// The peer we connected to.
+        peer: Peer,
+    },
+    /// An attempt to connect to a peer failed.
+    ConnectionFailure {
+        /// Address we failed to connect to.
+        peer_addr: SocketAddr,
+    },
+    /// A message was received from a peer.
+    NewMessage {
+        /// Address of the sender.
+        peer_addr: SocketAddr,
+        /// Message payload.
+        msg: Bytes,
+    },
+    /// `QuicP2p` is shutting down.
+    Finish,
+    /// A gossip message was received for the first time, either published by the sending peer
+    /// or forwarded through the mesh. Raised for every topic whose messages reach us, including
+    /// topics we only relay for other peers without having subscribed to them ourselves.
+    GossipMessage {
+        /// Topic the message was published on.
+        topic: String,
+        /// The mesh peer we received this copy from (not necessarily the original publisher).
+        source: SocketAddr,
+        /// The gossiped payload.
+        msg: Bytes,
+    },
+    /// A request arrived that expects a reply via `QuicP2p::send_response`.
+    IncomingRequest {
+        /// Correlates this request with the response the application should send back.
+        request_id: RequestId,
+        /// The peer that sent the request.
+        peer: Peer,
+        /// Request payload.
+        msg: Bytes,
+    },
+    /// A request sent via `QuicP2p::send_request` got no response within its deadline.
+    RequestTimeout {
+        /// The request that timed out.
+        request_id: RequestId,
+    },
+    /// `QuicP2p::bootstrap` successfully connected to a candidate from the bootstrap cache or
+    /// `Config::hard_coded_contacts`.
+    BootstrapedTo {
+        /// The node we bootstrapped to.
+        node: NodeInfo,
+    },
+    /// `QuicP2p::bootstrap` exhausted every candidate without a successful connection.
+    BootstrapFailure,
+    /// A peer's score dropped low enough that it has been disconnected and banned.
+    PeerBanned {
+        /// The banned peer.
+        peer_addr: SocketAddr,
+        /// The peer may not reconnect until this instant has passed.
+        until: Instant,
+    },
+    /// Periodic bandwidth snapshot, raised every `Config::bandwidth_report_interval_msec` if
+    /// configured.
+    BandwidthReport {
+        /// Global and per-peer byte counters and sliding-window rates.
+        stats: BandwidthStats,
+    },
+}
@@ -0,0 +1,253 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Gossipsub-style publish/subscribe over the mesh of connections already managed by
+//! [`QuicP2p`](crate::QuicP2p).
+//!
+//! Each topic gets its own bounded "mesh" of forwarding peers. Publishing (locally or on
+//! receipt of a forwarded message) fans the payload out to every mesh peer except the one it
+//! arrived from. A seen-message cache keyed by a content hash stops the flood from looping, and
+//! meshes below the low-water mark are repaired by grafting randomly-chosen subscribers.
+
+use crate::node_info::NodeInfo;
+use bytes::Bytes;
+use crc::crc32;
+use rand::seq::IteratorRandom;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Target number of forwarding peers maintained per topic mesh.
+pub const MESH_TARGET_DEGREE: usize = 6;
+/// Mesh is repaired by grafting once it drops below this many peers.
+pub const MESH_LOW_WATER: usize = 4;
+/// Peers beyond this count are pruned from the mesh.
+pub const MESH_HIGH_WATER: usize = 12;
+/// How long a message id is remembered for de-duplication purposes.
+const SEEN_CACHE_TTL: Duration = Duration::from_secs(2 * 60);
+/// How often a node re-announces the topics it knows about to every connected peer, so meshes
+/// that formed after the one-shot announcement at `subscribe`/connect time (or that lost a peer)
+/// still get repaired instead of staying broken forever.
+pub(crate) const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Content hash used to recognise a gossip message we've already forwarded.
+pub type MessageId = u32;
+
+pub(crate) fn message_id(topic: &str, msg: &Bytes) -> MessageId {
+    let mut hasher_input = Vec::with_capacity(topic.len() + msg.len());
+    hasher_input.extend_from_slice(topic.as_bytes());
+    hasher_input.extend_from_slice(msg);
+    crc32::checksum_ieee(&hasher_input)
+}
+
+/// The set of peers we currently forward a given topic's messages to, together with every peer
+/// known to be subscribed (a superset of the mesh, used as a graft candidate pool).
+#[derive(Default)]
+struct TopicState {
+    mesh: HashSet<SocketAddr>,
+    known_subscribers: HashSet<NodeInfo>,
+}
+
+/// Per-instance gossip bookkeeping: topic meshes plus the de-duplication cache.
+#[derive(Default)]
+pub(crate) struct GossipState {
+    topics: HashMap<String, TopicState>,
+    seen: HashMap<MessageId, Instant>,
+}
+
+impl GossipState {
+    /// Registers local interest in `topic`, creating an empty mesh if this is the first
+    /// subscriber.
+    pub(crate) fn subscribe(&mut self, topic: &str) {
+        self.topics.entry(topic.to_string()).or_default();
+    }
+
+    /// Every topic we're either locally subscribed to or have learned about from a peer's
+    /// announcement. Used to re-announce membership to other peers, which lets a node that never
+    /// subscribes to anything still relay mesh membership between the peers connected through it.
+    pub(crate) fn known_topics(&self) -> Vec<String> {
+        self.topics.keys().cloned().collect()
+    }
+
+    /// Records that `peer` announced membership of `topic`, making it a graft candidate, and
+    /// grafts it into the mesh immediately if we're below the low-water mark.
+    pub(crate) fn on_topic_announcement(&mut self, topic: &str, peer: NodeInfo) {
+        let state = self.topics.entry(topic.to_string()).or_default();
+        state.known_subscribers.insert(peer.clone());
+        if state.mesh.len() < MESH_LOW_WATER {
+            state.mesh.insert(peer.peer_addr);
+        }
+    }
+
+    /// Removes a disconnected peer from every topic's mesh and known-subscriber pool, so a dead
+    /// connection doesn't keep counting towards a mesh's size forever and block `repair_meshes`
+    /// from ever grafting a replacement in its place.
+    pub(crate) fn on_peer_disconnected(&mut self, peer_addr: SocketAddr) {
+        for state in self.topics.values_mut() {
+            state.mesh.remove(&peer_addr);
+            state.known_subscribers.retain(|n| n.peer_addr != peer_addr);
+        }
+    }
+
+    /// Repairs every topic mesh that has fallen below the low-water mark by grafting
+    /// randomly-chosen known subscribers back up to the target degree, and prunes any that have
+    /// grown past the high-water mark back down to the target degree.
+    pub(crate) fn repair_meshes(&mut self) {
+        let mut rng = rand::thread_rng();
+        for state in self.topics.values_mut() {
+            if state.mesh.len() < MESH_LOW_WATER {
+                while state.mesh.len() < MESH_TARGET_DEGREE {
+                    let candidate = state
+                        .known_subscribers
+                        .iter()
+                        .map(|n| n.peer_addr)
+                        .filter(|addr| !state.mesh.contains(addr))
+                        .choose(&mut rng);
+                    match candidate {
+                        Some(addr) => {
+                            state.mesh.insert(addr);
+                        }
+                        None => break,
+                    }
+                }
+            }
+            if state.mesh.len() > MESH_HIGH_WATER {
+                let excess = state.mesh.len() - MESH_TARGET_DEGREE;
+                let to_drop: Vec<SocketAddr> =
+                    state.mesh.iter().copied().take(excess).collect();
+                for addr in to_drop {
+                    state.mesh.remove(&addr);
+                }
+            }
+        }
+    }
+
+    /// Marks `id` as seen, returning `true` if it was already present (i.e. this message should
+    /// not be forwarded again).
+    pub(crate) fn check_and_insert_seen(&mut self, id: MessageId) -> bool {
+        self.evict_expired();
+        let already_seen = self.seen.contains_key(&id);
+        self.seen.insert(id, Instant::now());
+        already_seen
+    }
+
+    fn evict_expired(&mut self) {
+        let now = Instant::now();
+        self.seen
+            .retain(|_, inserted_at| now.duration_since(*inserted_at) < SEEN_CACHE_TTL);
+    }
+
+    /// The peers a message on `topic` should be forwarded to, excluding `sender` (the peer it
+    /// arrived from, or `None` for a locally-originated publish).
+    pub(crate) fn forward_targets(
+        &self,
+        topic: &str,
+        sender: Option<SocketAddr>,
+    ) -> Vec<SocketAddr> {
+        self.topics
+            .get(topic)
+            .map(|state| {
+                state
+                    .mesh
+                    .iter()
+                    .copied()
+                    .filter(|addr| Some(*addr) != sender)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_info(port: u16) -> NodeInfo {
+        NodeInfo {
+            peer_addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            peer_cert_der: Vec::new(),
+            services: Default::default(),
+        }
+    }
+
+    #[test]
+    fn check_and_insert_seen_reports_only_the_second_sighting() {
+        let mut state = GossipState::default();
+        let id = message_id("topic", &Bytes::from_static(b"payload"));
+
+        assert!(!state.check_and_insert_seen(id));
+        assert!(state.check_and_insert_seen(id));
+    }
+
+    #[test]
+    fn message_id_depends_on_both_topic_and_payload() {
+        let msg = Bytes::from_static(b"payload");
+        assert_ne!(message_id("topic-a", &msg), message_id("topic-b", &msg));
+    }
+
+    #[test]
+    fn topic_announcement_grafts_into_mesh_below_low_water() {
+        let mut state = GossipState::default();
+        state.subscribe("topic");
+        state.on_topic_announcement("topic", node_info(1));
+
+        assert_eq!(state.forward_targets("topic", None), vec![node_info(1).peer_addr]);
+    }
+
+    #[test]
+    fn topic_announcement_past_low_water_only_tracks_known_subscriber() {
+        let mut state = GossipState::default();
+        state.subscribe("topic");
+        for port in 0..MESH_LOW_WATER as u16 {
+            state.on_topic_announcement("topic", node_info(port));
+        }
+        assert_eq!(state.forward_targets("topic", None).len(), MESH_LOW_WATER);
+
+        let extra = node_info(MESH_LOW_WATER as u16);
+        state.on_topic_announcement("topic", extra.clone());
+
+        let targets = state.forward_targets("topic", None);
+        assert_eq!(targets.len(), MESH_LOW_WATER);
+        assert!(!targets.contains(&extra.peer_addr));
+    }
+
+    #[test]
+    fn forward_targets_excludes_the_sender() {
+        let mut state = GossipState::default();
+        state.subscribe("topic");
+        state.on_topic_announcement("topic", node_info(1));
+        state.on_topic_announcement("topic", node_info(2));
+
+        let targets = state.forward_targets("topic", Some(node_info(1).peer_addr));
+        assert_eq!(targets, vec![node_info(2).peer_addr]);
+    }
+
+    #[test]
+    fn forward_targets_for_unknown_topic_is_empty() {
+        let state = GossipState::default();
+        assert!(state.forward_targets("unknown", None).is_empty());
+    }
+
+    #[test]
+    fn on_peer_disconnected_removes_it_from_mesh_and_known_subscribers() {
+        let mut state = GossipState::default();
+        state.subscribe("topic");
+        state.on_topic_announcement("topic", node_info(1));
+        state.on_topic_announcement("topic", node_info(2));
+
+        state.on_peer_disconnected(node_info(1).peer_addr);
+
+        let targets = state.forward_targets("topic", None);
+        assert_eq!(targets, vec![node_info(2).peer_addr]);
+
+        // The departed peer shouldn't resurface as a graft candidate either.
+        state.repair_meshes();
+        assert_eq!(state.forward_targets("topic", None), vec![node_info(2).peer_addr]);
+    }
+}
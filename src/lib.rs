@@ -0,0 +1,46 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! # Quic-P2P
+//!
+//! Peer-to-peer networking, eventually over QUIC. Provides a small, synchronous-looking API
+//! ([`QuicP2p`]), with connection and message events delivered over a `crossbeam_channel`.
+//! Connections are currently carried over plain TCP (see [`transport`]) while the QUIC/TLS
+//! endpoint this crate is named for is still being built; everything above the transport deals
+//! only in [`wire_msg::WireMsg`] frames, so swapping the transport out shouldn't ripple.
+
+mod api;
+mod bandwidth;
+mod bootstrap_cache;
+mod builder;
+mod compression;
+mod config;
+mod error;
+mod event;
+mod gossip;
+mod node_info;
+mod peer;
+mod peer_manager;
+mod rpc;
+mod service_flags;
+mod transport;
+mod wire_msg;
+
+pub use api::QuicP2p;
+pub use bandwidth::{BandwidthStats, PeerBandwidth};
+pub use builder::Builder;
+pub use compression::Codec;
+pub use config::Config;
+pub use error::{QuicP2pError, Result};
+pub use event::Event;
+pub use node_info::NodeInfo;
+pub use peer::Peer;
+pub use peer_manager::ReportSource;
+pub use rpc::RequestId;
+pub use service_flags::ServiceFlags;
@@ -0,0 +1,25 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::service_flags::ServiceFlags;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// Information needed to connect to a peer and verify its identity.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct NodeInfo {
+    /// Address other peers should dial to reach this node.
+    pub peer_addr: SocketAddr,
+    /// DER-encoded self-signed certificate used to authenticate the QUIC connection.
+    pub peer_cert_der: Vec<u8>,
+    /// Capabilities this peer negotiated during the handshake. Defaults to empty for contacts
+    /// read from older, pre-negotiation configuration so forward/backward compatibility holds.
+    #[serde(default)]
+    pub services: ServiceFlags,
+}
@@ -0,0 +1,47 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::node_info::NodeInfo;
+use crate::service_flags::ServiceFlags;
+use std::net::SocketAddr;
+
+/// A connected remote party, distinguishing full nodes from lightweight clients.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Peer {
+    /// A full peer participating in routing, identified by its `NodeInfo`.
+    Node {
+        /// Connection details of the node.
+        node_info: NodeInfo,
+    },
+    /// A client connection that does not route traffic for others.
+    Client {
+        /// Address the client connected from.
+        peer_addr: SocketAddr,
+        /// Capabilities this client negotiated during the handshake.
+        services: ServiceFlags,
+    },
+}
+
+impl Peer {
+    /// Returns the address of this peer, regardless of its kind.
+    pub fn peer_addr(&self) -> SocketAddr {
+        match self {
+            Peer::Node { node_info } => node_info.peer_addr,
+            Peer::Client { peer_addr, .. } => *peer_addr,
+        }
+    }
+
+    /// Returns the capabilities this peer negotiated during the handshake.
+    pub fn services(&self) -> ServiceFlags {
+        match self {
+            Peer::Node { node_info } => node_info.services,
+            Peer::Client { services, .. } => *services,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Connection limits, peer scoring and banning.
+//!
+//! `connect_to` used to accept every peer handed to it and never let go; this module gives
+//! `QuicP2p` a bounded, self-defending connection set instead. Every connected peer carries a
+//! score that observable good/bad behaviour nudges up or down; peers that misbehave badly
+//! enough are banned for a cooldown period instead of merely being disconnected.
+
+use crate::node_info::NodeInfo;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Score a newly-connected peer starts out with.
+const INITIAL_SCORE: i32 = 0;
+/// Peers at or below this score are banned.
+const BAN_THRESHOLD: i32 = -100;
+/// How long a ban lasts before the peer may reconnect.
+const BAN_DURATION: Duration = Duration::from_secs(60 * 10);
+/// Default maximum total connections, if `Config` doesn't override it.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 100;
+/// Default fraction of `max_connections` reserved for outbound-only peers, protecting them from
+/// eviction pressure caused by a flood of inbound connections.
+pub const DEFAULT_OUTBOUND_HEADROOM_FACTOR: f32 = 0.25;
+/// Default maximum connections accepted from a single IP address.
+pub const DEFAULT_MAX_CONNECTIONS_PER_IP: usize = 3;
+
+/// Identifies the subsystem that observed a peer's (mis)behaviour, so scoring adjustments can
+/// be reasoned about and logged per-source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReportSource {
+    /// Reported by the application built on top of `QuicP2p` (e.g. a failed content hash check).
+    Application,
+    /// Reported by the gossip subsystem.
+    Gossip,
+    /// Reported by the RPC subsystem.
+    Rpc,
+    /// Reported by the wire framing layer itself (frames that fail to decompress or decode).
+    Framing,
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+struct ConnectedPeer {
+    score: i32,
+    direction: Direction,
+}
+
+/// Tracks connection limits, per-peer scores and the current ban list.
+pub(crate) struct PeerManager {
+    max_connections: usize,
+    outbound_headroom: usize,
+    max_connections_per_ip: usize,
+    peers: HashMap<SocketAddr, ConnectedPeer>,
+    banned: HashMap<SocketAddr, Instant>,
+}
+
+impl PeerManager {
+    pub(crate) fn new(
+        max_connections: Option<usize>,
+        outbound_headroom_factor: Option<f32>,
+        max_connections_per_ip: Option<usize>,
+    ) -> Self {
+        let max_connections = max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS);
+        let headroom_factor = outbound_headroom_factor.unwrap_or(DEFAULT_OUTBOUND_HEADROOM_FACTOR);
+        Self {
+            max_connections,
+            outbound_headroom: ((max_connections as f32) * headroom_factor) as usize,
+            max_connections_per_ip: max_connections_per_ip.unwrap_or(DEFAULT_MAX_CONNECTIONS_PER_IP),
+            peers: HashMap::new(),
+            banned: HashMap::new(),
+        }
+    }
+
+    /// Returns `Some(until)` if `peer_addr` is currently banned.
+    pub(crate) fn ban_status(&mut self, peer_addr: SocketAddr) -> Option<Instant> {
+        match self.banned.get(&peer_addr) {
+            Some(until) if *until > Instant::now() => Some(*until),
+            Some(_) => {
+                self.banned.remove(&peer_addr);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns `true` if accepting `addr` would not exceed the per-IP connection limit.
+    pub(crate) fn ip_limit_ok(&self, addr: SocketAddr) -> bool {
+        self.count_for_ip(addr.ip()) < self.max_connections_per_ip
+    }
+
+    fn count_for_ip(&self, ip: IpAddr) -> usize {
+        self.peers.keys().filter(|a| a.ip() == ip).count()
+    }
+
+    /// Registers a newly-established connection and, if that puts us over the connection limit,
+    /// evicts the lowest-scoring non-reserved peer (which may be the new one), returning its
+    /// address.
+    pub(crate) fn on_connected(&mut self, node_info: &NodeInfo, outbound: bool) -> Option<SocketAddr> {
+        let direction = if outbound {
+            Direction::Outbound
+        } else {
+            Direction::Inbound
+        };
+        self.peers.insert(
+            node_info.peer_addr,
+            ConnectedPeer {
+                score: INITIAL_SCORE,
+                direction,
+            },
+        );
+
+        if self.peers.len() <= self.max_connections {
+            return None;
+        }
+        self.evict_lowest_scoring()
+    }
+
+    pub(crate) fn on_disconnected(&mut self, peer_addr: SocketAddr) {
+        self.peers.remove(&peer_addr);
+    }
+
+    /// Finds the lowest-scoring peer outside the reserved outbound headroom and removes it from
+    /// the tracked set, returning its address so the caller can actually close the connection.
+    fn evict_lowest_scoring(&mut self) -> Option<SocketAddr> {
+        let reserved_outbound = self.outbound_headroom;
+        let outbound_count = self
+            .peers
+            .values()
+            .filter(|p| matches!(p.direction, Direction::Outbound))
+            .count();
+
+        let victim = self
+            .peers
+            .iter()
+            .filter(|(_, p)| {
+                !(matches!(p.direction, Direction::Outbound) && outbound_count <= reserved_outbound)
+            })
+            .min_by_key(|(_, p)| p.score)
+            .map(|(addr, _)| *addr);
+
+        if let Some(addr) = victim {
+            self.peers.remove(&addr);
+        }
+        victim
+    }
+
+    /// Adjusts `peer_addr`'s score by `delta` (positive for useful behaviour, negative for
+    /// violations), banning the peer for `BAN_DURATION` if it drops to or below the threshold.
+    /// Returns the ban deadline if this call caused a new ban.
+    pub(crate) fn adjust_score(
+        &mut self,
+        peer_addr: SocketAddr,
+        _source: ReportSource,
+        delta: i32,
+    ) -> Option<Instant> {
+        let score = match self.peers.get_mut(&peer_addr) {
+            Some(peer) => {
+                peer.score += delta;
+                peer.score
+            }
+            None => return None,
+        };
+
+        if score <= BAN_THRESHOLD {
+            self.peers.remove(&peer_addr);
+            let until = Instant::now() + BAN_DURATION;
+            self.banned.insert(peer_addr, until);
+            Some(until)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn node_info(port: u16) -> NodeInfo {
+        NodeInfo {
+            peer_addr: addr(port),
+            peer_cert_der: Vec::new(),
+            services: Default::default(),
+        }
+    }
+
+    #[test]
+    fn adjust_score_bans_once_threshold_is_reached() {
+        let mut pm = PeerManager::new(None, None, None);
+        pm.on_connected(&node_info(1), true);
+
+        assert!(pm.adjust_score(addr(1), ReportSource::Framing, BAN_THRESHOLD + 1).is_none());
+        assert!(pm.ban_status(addr(1)).is_none());
+
+        assert!(pm.adjust_score(addr(1), ReportSource::Framing, -1).is_some());
+        assert!(pm.ban_status(addr(1)).is_some());
+    }
+
+    #[test]
+    fn adjust_score_is_a_no_op_for_an_untracked_peer() {
+        let mut pm = PeerManager::new(None, None, None);
+        assert!(pm.adjust_score(addr(1), ReportSource::Framing, -1000).is_none());
+    }
+
+    #[test]
+    fn ip_limit_ok_respects_max_connections_per_ip() {
+        let mut pm = PeerManager::new(None, None, Some(1));
+        assert!(pm.ip_limit_ok(addr(1)));
+
+        pm.on_connected(&node_info(1), true);
+        assert!(!pm.ip_limit_ok(addr(2)));
+    }
+
+    #[test]
+    fn on_connected_evicts_lowest_scoring_inbound_peer_over_the_limit() {
+        let mut pm = PeerManager::new(Some(2), Some(0.0), None);
+        pm.on_connected(&node_info(1), false);
+        pm.adjust_score(addr(1), ReportSource::Framing, -5);
+        pm.on_connected(&node_info(2), false);
+
+        let evicted = pm.on_connected(&node_info(3), false);
+        assert_eq!(evicted, Some(addr(1)));
+    }
+
+    #[test]
+    fn ban_status_is_none_for_a_peer_that_was_never_banned() {
+        let mut pm = PeerManager::new(None, None, None);
+        assert!(pm.ban_status(addr(1)).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Typed request/response support, carried over the same connections as `QuicP2p::send` in
+//! dedicated `WireMsg::Request`/`WireMsg::Response` frames.
+//!
+//! Every outgoing request is tagged with a [`RequestId`] that the peer echoes back in its
+//! response, so a caller doesn't have to demultiplex replies by peer address the way the
+//! `client_node` example used to for bootstrap traffic. A request that never gets a reply within
+//! its deadline is cancelled and reported via `Event::RequestTimeout`.
+
+use bytes::Bytes;
+use crossbeam_channel as mpmc;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Identifies one outstanding request/response exchange.
+pub type RequestId = u64;
+
+/// Default time a request is allowed to remain unanswered before it is cancelled.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Allocates a fresh, process-wide unique `RequestId`.
+pub(crate) fn next_request_id() -> RequestId {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+struct Outstanding {
+    peer_addr: SocketAddr,
+    deadline: Instant,
+    reply_tx: mpmc::Sender<Bytes>,
+}
+
+/// Tracks requests this node has sent and is still waiting on a response for.
+#[derive(Default)]
+pub(crate) struct RequestTable {
+    outstanding: HashMap<RequestId, Outstanding>,
+}
+
+impl RequestTable {
+    pub(crate) fn insert(
+        &mut self,
+        request_id: RequestId,
+        peer_addr: SocketAddr,
+        timeout: Duration,
+    ) -> mpmc::Receiver<Bytes> {
+        let (reply_tx, reply_rx) = mpmc::bounded(1);
+        self.outstanding.insert(
+            request_id,
+            Outstanding {
+                peer_addr,
+                deadline: Instant::now() + timeout,
+                reply_tx,
+            },
+        );
+        reply_rx
+    }
+
+    /// Delivers a response, returning `true` if a matching request was found.
+    pub(crate) fn resolve(&mut self, request_id: RequestId, msg: Bytes) -> bool {
+        match self.outstanding.remove(&request_id) {
+            Some(outstanding) => {
+                let _ = outstanding.reply_tx.send(msg);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every outstanding request addressed to `peer_addr`, e.g. on disconnect.
+    pub(crate) fn cancel_for_peer(&mut self, peer_addr: SocketAddr) -> Vec<RequestId> {
+        let expired: Vec<RequestId> = self
+            .outstanding
+            .iter()
+            .filter(|(_, o)| o.peer_addr == peer_addr)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &expired {
+            self.outstanding.remove(id);
+        }
+        expired
+    }
+
+    /// Removes `request_id` if it is still outstanding and its deadline has passed, returning
+    /// `true` if it was timed out this way. A request already resolved or cancelled is left
+    /// untouched (returns `false`).
+    pub(crate) fn take_if_expired(&mut self, request_id: RequestId) -> bool {
+        match self.outstanding.get(&request_id) {
+            Some(o) if o.deadline <= Instant::now() => {
+                self.outstanding.remove(&request_id);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn resolve_delivers_the_response_to_the_matching_receiver() {
+        let mut table = RequestTable::default();
+        let id = next_request_id();
+        let reply_rx = table.insert(id, addr(1), Duration::from_secs(30));
+
+        assert!(table.resolve(id, Bytes::from_static(b"pong")));
+        assert_eq!(reply_rx.recv().unwrap(), Bytes::from_static(b"pong"));
+    }
+
+    #[test]
+    fn resolve_for_an_unknown_request_id_is_a_no_op() {
+        let mut table = RequestTable::default();
+        assert!(!table.resolve(next_request_id(), Bytes::new()));
+    }
+
+    #[test]
+    fn cancel_for_peer_only_drops_requests_addressed_to_that_peer() {
+        let mut table = RequestTable::default();
+        let id_a = next_request_id();
+        let id_b = next_request_id();
+        table.insert(id_a, addr(1), Duration::from_secs(30));
+        table.insert(id_b, addr(2), Duration::from_secs(30));
+
+        assert_eq!(table.cancel_for_peer(addr(1)), vec![id_a]);
+        assert!(!table.resolve(id_a, Bytes::new()));
+        assert!(table.resolve(id_b, Bytes::new()));
+    }
+
+    #[test]
+    fn take_if_expired_is_false_before_the_deadline() {
+        let mut table = RequestTable::default();
+        let id = next_request_id();
+        table.insert(id, addr(1), Duration::from_secs(30));
+
+        assert!(!table.take_if_expired(id));
+    }
+
+    #[test]
+    fn take_if_expired_removes_the_request_exactly_once_past_the_deadline() {
+        let mut table = RequestTable::default();
+        let id = next_request_id();
+        table.insert(id, addr(1), Duration::from_millis(1));
+        thread::sleep(Duration::from_millis(20));
+
+        assert!(table.take_if_expired(id));
+        assert!(!table.take_if_expired(id));
+    }
+}
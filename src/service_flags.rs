@@ -0,0 +1,124 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Capabilities a peer advertises during the connection handshake, so callers don't have to
+//! assume every peer is a full `Peer::Node` the way the `client_node` example used to.
+
+use serde::{Deserialize, Serialize};
+use std::num::ParseIntError;
+use std::ops::{BitOr, BitOrAssign};
+use std::str::FromStr;
+
+/// Bitfield of capabilities a peer supports, exchanged during the handshake.
+///
+/// Unused bits are reserved for future flags: an endpoint that receives bits it doesn't
+/// recognise keeps them (so they round-trip through `peer.services()`) but otherwise ignores
+/// them, rather than rejecting the connection.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ServiceFlags(u32);
+
+impl ServiceFlags {
+    /// Peer will relay messages on behalf of others.
+    pub const RELAY: ServiceFlags = ServiceFlags(1 << 0);
+    /// Peer will store and serve data on request.
+    pub const STORE: ServiceFlags = ServiceFlags(1 << 1);
+    /// Peer participates in gossip topic meshes.
+    pub const GOSSIP: ServiceFlags = ServiceFlags(1 << 2);
+    /// Peer is a full node offering every service above.
+    pub const FULL: ServiceFlags =
+        ServiceFlags(Self::RELAY.0 | Self::STORE.0 | Self::GOSSIP.0);
+
+    /// No capabilities advertised.
+    pub const fn empty() -> Self {
+        ServiceFlags(0)
+    }
+
+    /// Returns `true` if every flag set in `other` is also set in `self`.
+    pub fn contains(self, other: ServiceFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The raw bitfield, including any bits this version of the crate doesn't recognise.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Builds a `ServiceFlags` from a raw bitfield, preserving unknown bits unchanged.
+    pub fn from_bits_retain(bits: u32) -> Self {
+        ServiceFlags(bits)
+    }
+}
+
+impl BitOr for ServiceFlags {
+    type Output = ServiceFlags;
+
+    fn bitor(self, rhs: ServiceFlags) -> ServiceFlags {
+        ServiceFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ServiceFlags {
+    fn bitor_assign(&mut self, rhs: ServiceFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl FromStr for ServiceFlags {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u32>().map(ServiceFlags::from_bits_retain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_requires_every_flag_set_in_other() {
+        assert!(ServiceFlags::FULL.contains(ServiceFlags::GOSSIP));
+        assert!(!ServiceFlags::GOSSIP.contains(ServiceFlags::STORE));
+        assert!(ServiceFlags::empty().contains(ServiceFlags::empty()));
+    }
+
+    #[test]
+    fn bitor_combines_flags() {
+        let combined = ServiceFlags::RELAY | ServiceFlags::GOSSIP;
+        assert!(combined.contains(ServiceFlags::RELAY));
+        assert!(combined.contains(ServiceFlags::GOSSIP));
+        assert!(!combined.contains(ServiceFlags::STORE));
+    }
+
+    #[test]
+    fn bitor_assign_combines_flags_in_place() {
+        let mut flags = ServiceFlags::RELAY;
+        flags |= ServiceFlags::STORE;
+        assert!(flags.contains(ServiceFlags::RELAY | ServiceFlags::STORE));
+    }
+
+    #[test]
+    fn from_bits_retain_preserves_bits_this_version_does_not_recognise() {
+        let unknown_bit = 1 << 30;
+        let flags = ServiceFlags::from_bits_retain(unknown_bit);
+        assert_eq!(flags.bits(), unknown_bit);
+        assert!(!flags.contains(ServiceFlags::FULL));
+    }
+
+    #[test]
+    fn from_str_parses_a_raw_bitfield() {
+        let flags: ServiceFlags = "5".parse().unwrap();
+        assert_eq!(flags.bits(), 5);
+    }
+
+    #[test]
+    fn from_str_rejects_non_numeric_input() {
+        assert!("gossip".parse::<ServiceFlags>().is_err());
+    }
+}
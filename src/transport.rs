@@ -0,0 +1,183 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Real, OS-socket-backed networking.
+//!
+//! Every connection is a plain TCP stream framed with a 4-byte big-endian length prefix
+//! followed by whatever bytes `QuicP2p`'s send path already produced (a compressed,
+//! bincode-encoded [`WireMsg`](crate::wire_msg::WireMsg)). A background thread accepts inbound
+//! connections and one reader thread per connection decodes frames and dispatches them back
+//! into `QuicP2p::on_frame_received`, so the handlers the rest of this crate defines actually
+//! see traffic instead of sitting unreachable.
+//!
+//! This is not yet the QUIC/TLS transport the crate is named for: there's no encryption,
+//! multiplexed streams or certificate-based peer authentication here, only the plumbing needed
+//! to get real bytes between two `QuicP2p` instances. Swapping this module out for a real QUIC
+//! endpoint shouldn't need to change anything above it, since callers only ever see `WireMsg`
+//! frames going in and out.
+
+use crate::api::{self, QuicP2p};
+use crate::node_info::NodeInfo;
+use crate::service_flags::ServiceFlags;
+use bytes::Bytes;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Frames larger than this are rejected outright, so a corrupt or hostile length prefix can't
+/// make us allocate an unbounded buffer before we even get to decompress/deserialise it. Used
+/// unless `Config::max_msg_size_allowed` overrides it.
+const DEFAULT_MAX_FRAME_BYTES: u32 = 64 * 1024 * 1024;
+
+/// The write half of one peer's socket, kept behind a `Mutex` since `QuicP2p::send` can be
+/// called from any thread holding a handle.
+pub(crate) struct Connection {
+    writer: Mutex<TcpStream>,
+}
+
+impl Connection {
+    /// Writes `body` to the peer as one length-prefixed frame.
+    pub(crate) fn send_frame(&self, body: &[u8]) -> io::Result<()> {
+        let len = body.len() as u32;
+        let mut writer = lock(&self.writer);
+        writer.write_all(&len.to_be_bytes())?;
+        writer.write_all(body)
+    }
+
+    /// Closes both halves of the underlying socket, unblocking its reader thread.
+    pub(crate) fn shutdown(&self) {
+        let _ = lock(&self.writer).shutdown(Shutdown::Both);
+    }
+}
+
+/// Binds a listener on `qp2p.our_info.peer_addr` and spawns a background thread, holding its own
+/// clone of `qp2p`, that accepts inbound connections for the rest of the process's lifetime and
+/// registers each one with the peer manager. Returns the address actually bound to, which
+/// differs from the configured one when the configured port was `0`.
+pub(crate) fn listen(qp2p: &QuicP2p) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(qp2p.our_info.peer_addr)?;
+    let local_addr = listener.local_addr()?;
+    let qp2p = qp2p.clone();
+    std::thread::spawn(move || {
+        for incoming in listener.incoming() {
+            let stream = match incoming {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let peer_addr = match stream.peer_addr() {
+                Ok(addr) => addr,
+                Err(_) => continue,
+            };
+            if !accept_allowed(&qp2p, peer_addr) {
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            let inbound_info = NodeInfo {
+                peer_addr,
+                peer_cert_der: Vec::new(),
+                services: ServiceFlags::empty(),
+            };
+            let evicted = api::unwrap_lock(&qp2p.peer_manager).on_connected(&inbound_info, false);
+            if evicted == Some(peer_addr) {
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            spawn_connection(qp2p.clone(), peer_addr, stream);
+            if let Some(evicted_addr) = evicted {
+                qp2p.clone().disconnect_from(evicted_addr);
+            }
+        }
+    });
+    Ok(local_addr)
+}
+
+/// Rejects an inbound connection from a banned peer or one that would push its source IP over
+/// `max_connections_per_ip`, before we ever spawn a reader thread or hand it a socket to write
+/// to.
+fn accept_allowed(qp2p: &QuicP2p, peer_addr: SocketAddr) -> bool {
+    let mut peer_manager = api::unwrap_lock(&qp2p.peer_manager);
+    if peer_manager.ban_status(peer_addr).is_some() {
+        return false;
+    }
+    peer_manager.ip_limit_ok(peer_addr)
+}
+
+/// Opens an outbound connection to `peer_addr`, registering it for future `send_wire` calls and
+/// spawning a reader thread for inbound frames. A no-op if we're already connected.
+pub(crate) fn connect(qp2p: &QuicP2p, peer_addr: SocketAddr) -> io::Result<()> {
+    if api::unwrap_lock(&qp2p.sockets).contains_key(&peer_addr) {
+        return Ok(());
+    }
+    let stream = TcpStream::connect(peer_addr)?;
+    spawn_connection(qp2p.clone(), peer_addr, stream);
+    Ok(())
+}
+
+fn spawn_connection(mut qp2p: QuicP2p, peer_addr: SocketAddr, stream: TcpStream) {
+    let writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    if let Some(idle_timeout_msec) = qp2p.config.idle_timeout_msec {
+        let _ = stream.set_read_timeout(Some(Duration::from_millis(idle_timeout_msec)));
+    }
+    api::unwrap_lock(&qp2p.sockets).insert(
+        peer_addr,
+        Arc::new(Connection {
+            writer: Mutex::new(writer),
+        }),
+    );
+    qp2p.send_handshake(peer_addr);
+    qp2p.announce_known_topics_to(peer_addr);
+    let max_frame_bytes = qp2p.config.max_msg_size_allowed.unwrap_or(DEFAULT_MAX_FRAME_BYTES);
+    std::thread::spawn(move || read_loop(qp2p, peer_addr, stream, max_frame_bytes));
+}
+
+fn read_loop(mut qp2p: QuicP2p, peer_addr: SocketAddr, mut stream: TcpStream, max_frame_bytes: u32) {
+    loop {
+        match read_frame(&mut stream, max_frame_bytes) {
+            Ok(Some(body)) => qp2p.on_frame_received(peer_addr, Bytes::from(body)),
+            // `Err` also covers a read timing out because `Config::idle_timeout_msec` elapsed
+            // without the peer sending anything, which we treat the same as it hanging up.
+            Ok(None) | Err(_) => {
+                qp2p.on_socket_closed(peer_addr);
+                return;
+            }
+        }
+    }
+}
+
+fn read_frame(stream: &mut TcpStream, max_frame_bytes: u32) -> io::Result<Option<Vec<u8>>> {
+    let mut len_buf = [0u8; 4];
+    if let Err(e) = stream.read_exact(&mut len_buf) {
+        return if e.kind() == io::ErrorKind::UnexpectedEof {
+            Ok(None)
+        } else {
+            Err(e)
+        };
+    }
+    let len = u32::from_be_bytes(len_buf);
+    if len > max_frame_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame exceeds max_msg_size_allowed",
+        ));
+    }
+    let mut body = vec![0u8; len as usize];
+    stream.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn lock(mutex: &Mutex<TcpStream>) -> std::sync::MutexGuard<'_, TcpStream> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
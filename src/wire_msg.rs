@@ -0,0 +1,64 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Envelope for every frame exchanged between peers.
+//!
+//! Every frame carries exactly one `WireMsg`, bincode-encoded (then tagged with its compression
+//! codec by the send path and length-prefixed by the transport). Keeping the envelope in one
+//! place means new message kinds (RPC framing, gossip control, etc.) extend this enum instead
+//! of inventing their own ad-hoc header.
+
+use crate::compression::Codec;
+use crate::rpc::RequestId;
+use crate::service_flags::ServiceFlags;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Top-level envelope carried by every frame.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum WireMsg {
+    /// Sent immediately after a connection is established, before any application traffic, so
+    /// each side learns the other's actual advertised capabilities instead of assuming its own
+    /// configuration applies to the peer too.
+    Handshake {
+        /// Capabilities the sender supports.
+        services: ServiceFlags,
+        /// Codec the sender would like frames addressed to it to be compressed with, if at all.
+        preferred_codec: Codec,
+    },
+    /// An application payload passed verbatim to `Event::NewMessage`.
+    UserMsg(Bytes),
+    /// A request expecting a matching `Response` carrying the same `request_id`.
+    Request {
+        /// Correlates this request with its eventual response.
+        request_id: RequestId,
+        /// Request payload.
+        msg: Bytes,
+    },
+    /// A reply to a previously received `Request`.
+    Response {
+        /// Echoes the `request_id` of the request being answered.
+        request_id: RequestId,
+        /// Response payload.
+        msg: Bytes,
+    },
+    /// A message published or forwarded on a gossip topic.
+    Gossip {
+        /// Topic the message belongs to.
+        topic: String,
+        /// The gossiped payload.
+        msg: Bytes,
+    },
+    /// Announcement that the sender subscribes to or relays `topic`, sent on subscribe, on
+    /// connect and periodically, used to repair meshes that have fallen below the low-water mark.
+    GossipTopicAnnouncement {
+        /// Topic the sender subscribes to or relays.
+        topic: String,
+    },
+}